@@ -1,13 +1,19 @@
-/// WASAPI per-process audio capture module.
+/// Per-process / loopback audio capture module.
 ///
-/// Windows WASAPI API'nin PROCESS_LOOPBACK_MODE_EXCLUDE_TARGET_PROCESS_TREE
+/// Windows'ta WASAPI'nin PROCESS_LOOPBACK_MODE_EXCLUDE_TARGET_PROCESS_TREE
 /// modunu kullanarak kendi uygulamamızın ses çıkışını hariç tutup geri kalan
 /// tüm sistem sesini yakalar. Bu sayede ekran paylaşımında voice chat sesi
 /// (kendi WebView'umuzdan çıkan) yakalanmaz — echo olmaz.
 ///
-/// Veri akışı:
+/// macOS/Linux'ta ise WASAPI'nin eşdeğeri (per-process loopback) yok —
+/// bunun yerine `cpal` üzerinden sistemin loopback/monitor input cihazını
+/// (PulseAudio/PipeWire monitor source ya da macOS aggregate/loopback device)
+/// açıp oradan capture yaparız. Bu yol per-process değil, per-device'dır;
+/// aşağıdaki "Echo riski" bölümüne bakın.
+///
+/// Veri akışı (her iki platformda da aynı):
 /// ```text
-/// WASAPI capture (f32 48kHz stereo)
+/// Platform capture (WASAPI ya da cpal)
 ///   → f32→i16 dönüşüm (Rust)
 ///   → Tauri IPC event ("audio-pcm")
 ///   → Frontend AudioWorklet (pcm-worklet-processor.js)
@@ -16,353 +22,1109 @@
 /// ```
 ///
 /// Neden f32 capture + i16 dönüşüm?
-/// WASAPI shared mode doğal olarak f32 (IEEE Float) kullanır. autoconvert ile i16
-/// isteyebiliriz ama dönüşümü kendimiz yapmak daha güvenilir ve kontrol sağlar.
+/// Hem WASAPI shared mode hem de cpal'in varsayılan input config'i f32
+/// (IEEE Float) kullanır. autoconvert/cpal resampling ile i16 isteyebiliriz
+/// ama dönüşümü kendimiz yapmak daha güvenilir ve kontrol sağlar.
 ///
 /// Chunk boyutu: 20ms = 960 frame × 2 kanal = 1920 i16 sample = 3840 byte
 /// IPC hızı: ~50 event/saniye — Tauri local IPC için sorunsuz (~192KB/s)
 ///
 /// Platform desteği:
-/// - Windows 10 Build 20348+: Tam destek (WASAPI per-process loopback)
-/// - Eski Windows / macOS / Linux: start() hata döner, uygulama sessizce devam eder
-
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+/// - Windows 10 Build 20348+: Tam destek (WASAPI per-process loopback, echo yok)
+/// - macOS / Linux: cpal loopback/monitor backend (per-device, "Echo riski"ne bakın)
+/// - Diğerleri (eski Windows, loopback cihazı olmayan cpal host): start()
+///   yine bir `CaptureId` döner; capture thread'i hatayı log'layıp kapanır,
+///   hiç "audio-pcm" event'i gelmez, uygulama sessizce devam eder
+///
+/// Echo riski (macOS/Linux):
+/// WASAPI'nin EXCLUDE modunun aksine, PulseAudio/PipeWire monitor source ve
+/// macOS aggregate device kendi process'imizin çıkışını ayıramaz — o sink'e
+/// giden HER ŞEYİ yakalar. Bu da kendi WebView'umuzdan çıkan voice chat
+/// sesinin capture'a karışması (echo) demektir. Bu yüzden bu platformlarda
+/// capture'ı sadece uygulamanın kendi playback'i susturulmuşken ya da ayrı
+/// bir sink'e yönlendirilmişken etkinleştirin — `start()` bunu otomatik
+/// garanti etmez, çağıran taraf (frontend) bu ön koşulu sağlamalı.
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use tauri::AppHandle;
 
+/// Frontend'in talep edebileceği capture formatı.
+///
+/// Önceden `WaveFormat::new(32, 32, Float, 48000, 2, None)` ve 960 frame/20ms
+/// chunk sabit kodlanmıştı. Artık çağıran taraf (ör. bant genişliği kısıtlı
+/// bir görüşme için 16kHz mono, ya da müzik paylaşımı için 48kHz stereo)
+/// bunu seçebilir. `autoconvert: true` korunur — WASAPI cihazın native
+/// formatından istenen formata köprü kurar.
+#[derive(Clone, Copy, Debug, serde::Deserialize)]
+pub struct CaptureConfig {
+    /// Saniyedeki sample sayısı, ör. 16000, 44100, 48000.
+    pub sample_rate: u32,
+    /// Kanal sayısı — 1 (mono) veya 2 (stereo).
+    pub channels: u16,
+    /// Bir IPC chunk'ının kapsadığı süre (milisaniye). Daha küçük değer
+    /// daha düşük gecikme ama daha yüksek IPC event hızı demektir.
+    pub chunk_ms: u32,
+}
+
+impl Default for CaptureConfig {
+    /// Önceki sabit kodlanmış değerler: 48kHz stereo, 20ms chunk.
+    fn default() -> Self {
+        Self {
+            sample_rate: 48_000,
+            channels: 2,
+            chunk_ms: 20,
+        }
+    }
+}
+
+impl CaptureConfig {
+    /// `sample_rate * chunk_ms / 1000` ile chunk'ın frame sayısını hesaplar.
+    fn chunk_frames(&self) -> usize {
+        (self.sample_rate as usize * self.chunk_ms as usize) / 1000
+    }
+
+    /// Bir frame'in byte boyutu: `channels × 4` (f32 sample başına 4 byte).
+    ///
+    /// Sadece WASAPI backend'i kullanır — cpal backend'i byte değil sample
+    /// sayısıyla çalışır. `cfg(windows)` olmadan macOS/Linux derlemesinde
+    /// "method is never used" uyarısı (`-D warnings` altında hata) üretirdi.
+    #[cfg(windows)]
+    fn blockalign(&self) -> usize {
+        self.channels as usize * 4
+    }
+
+    /// Config'i makul sınırlar içinde doğrular.
+    ///
+    /// Burada cihaza danışmıyoruz — `autoconvert: true` WASAPI'nin kendi
+    /// native formatıyla istenen format arasında köprü kurmasını sağlar,
+    /// dolayısıyla asıl risk donanımın desteklemediği bir format değil,
+    /// anlamsız/bozuk bir istektir (ör. 0 Hz, 0 kanal). Bu yüzden sadece
+    /// temsil edilebilirlik kontrolü yapıyoruz — sessizce bozuk ses üretmek
+    /// yerine açık bir hata dönüyoruz.
+    fn validate(&self) -> Result<(), String> {
+        if !(8_000..=192_000).contains(&self.sample_rate) {
+            return Err(format!(
+                "Unsupported sample rate: {} Hz (expected 8000-192000)",
+                self.sample_rate
+            ));
+        }
+        if !(1..=2).contains(&self.channels) {
+            return Err(format!(
+                "Unsupported channel count: {} (expected 1 or 2)",
+                self.channels
+            ));
+        }
+        if !(1..=1000).contains(&self.chunk_ms) {
+            return Err(format!(
+                "Unsupported chunk duration: {} ms (expected 1-1000)",
+                self.chunk_ms
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Bir capture cihazının desteklediği bir sample rate / kanal sayısı çifti.
+///
+/// `query_supported_formats()` komutu ile frontend'e döner — kullanıcı bir
+/// `CaptureConfig` oluştururken cihazın gerçekten destekleyeceği bir
+/// kombinasyonu seçebilir.
+#[derive(Clone, Copy, serde::Serialize)]
+pub struct SupportedFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+/// Capture'ın açacağı loopback cihazının desteklediği formatları enumerate
+/// eder (Windows'ta process-loopback client, macOS/Linux'ta cpal loopback/
+/// monitor cihazı — varsayılan mikrofon değil).
+#[cfg(windows)]
+pub fn query_supported_formats() -> Result<Vec<SupportedFormat>, String> {
+    wasapi_backend::query_supported_formats()
+}
+
+#[cfg(not(windows))]
+pub fn query_supported_formats() -> Result<Vec<SupportedFormat>, String> {
+    cpal_backend::query_supported_formats()
+}
+
+/// Çalışan bir capture oturumunu tanımlayan stabil kimlik.
+///
+/// `CaptureManager::start*` tarafından üretilir, `stop`/`pause`/`resume`'a ve
+/// emit edilen her `PcmChunk`'a eşlik eder — frontend birden fazla eşzamanlı
+/// kaynağı (ör. bir INCLUDE-mode oyun akışı + bir EXCLUDE-mode sistem akışı)
+/// bu id ile ayırt eder.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, serde::Serialize, serde::Deserialize)]
+pub struct CaptureId(u64);
+
 /// Tauri IPC event'i ile gönderilen PCM ses chunk'ı.
 ///
 /// Frontend bu payload'u `listen("audio-pcm")` ile alır ve AudioWorklet'e iletir.
-/// Samples: interleaved stereo i16 — [L, R, L, R, ...]
-/// Her chunk 20ms ses verisi içerir (960 frame × 2 kanal = 1920 sample).
+/// `id`, chunk'ın hangi capture oturumundan geldiğini belirtir — birden fazla
+/// eşzamanlı capture varsa frontend bunları `id`'ye göre ayrı track'lere yönlendirir.
+/// Samples: interleaved i16 — [L, R, L, R, ...] (stereo) ya da mono.
+///
+/// `sample_rate`/`channels` chunk'ın GERÇEKTEN hangi formatta olduğunu
+/// belirtir. WASAPI backend'inde bu her zaman istenen `CaptureConfig` ile
+/// birebir aynıdır (autoconvert köprü kurar); cpal backend'inde cihaz
+/// `CaptureConfig`'i tam karşılamıyorsa cihazın ilk f32 config'ine (onun en
+/// yüksek sample rate'i ve kanal sayısıyla) düşülebilir — frontend decode'u isteneni değil, burada duyurulanı
+/// kullanmalı, aksi halde yanlış hız/kanal sayısıyla decode edilmiş
+/// bozuk/perde kaymış ses elde edilir.
 #[derive(Clone, serde::Serialize)]
 pub struct PcmChunk {
-    /// Interleaved stereo i16 PCM samples.
-    /// Uzunluk: 1920 (960 frame × 2 kanal)
+    /// Bu chunk'ı üreten capture oturumunun id'si.
+    pub id: CaptureId,
+    /// Chunk'ın gerçek sample rate'i (Hz).
+    pub sample_rate: u32,
+    /// Chunk'ın gerçek kanal sayısı.
+    pub channels: u16,
+    /// Interleaved i16 PCM samples, `channels` kanala göre interleave edilmiş.
     /// Değer aralığı: [-32767, 32767]
     pub samples: Vec<i16>,
 }
 
-/// WASAPI per-process audio capture controller.
+/// Tek bir çalışan capture oturumuna ait kontrol kancaları.
 ///
-/// `start()` bir background thread başlatır, `stop()` ile durdurulur.
-/// Thread-safe: `AtomicBool` ile start/stop senkronize edilir.
+/// Background thread bu handle'ın `running`/`paused` flag'lerini paylaşır.
+/// Thread'in kendisi join edilmez — `stop()` `running`'i false yapar, thread
+/// bir sonraki event/poll iterasyonunda kendi kendine kapanır.
+struct CaptureHandle {
+    /// true = thread çalışıyor, false = dur sinyali verildi veya durdu.
+    running: Arc<AtomicBool>,
+    /// true = WASAPI/cpal stream'i canlı ama emit durduruldu (instant resume).
+    paused: Arc<AtomicBool>,
+}
+
+/// Birden fazla eşzamanlı per-process / loopback capture oturumunu yönetir.
+///
+/// Her oturum kendi background thread'inde, kendi `running`/`paused`
+/// flag'leriyle çalışır — bir oturumu durdurmak/duraklatmak diğerlerini
+/// etkilemez. `CaptureId`ler tek bir `AtomicU64` sayaçtan artarak üretilir,
+/// bu yüzden süreç boyunca biriciktir (process restart'ta sıfırlanır).
 ///
 /// Yaşam döngüsü:
-/// 1. `new()` → Controller oluştur (henüz capture yok)
-/// 2. `start(app)` → Background thread başlat, PCM event'leri yayınla
-/// 3. `stop()` → Flag'i false yap, thread temiz kapansın
-/// 4. Thread otomatik olarak flag'i false yapar (hata veya normal kapanış)
-pub struct AudioCapture {
-    /// Background capture thread'in çalışma durumu.
-    /// true = çalışıyor, false = dur sinyali verildi veya durdu.
-    /// SeqCst ordering: tüm thread'lerde tutarlı görünürlük.
-    running: Arc<AtomicBool>,
+/// 1. `new()` → Boş manager oluştur (henüz oturum yok)
+/// 2. `start(app, pid, include)` → Yeni `CaptureId` üret, background thread
+///    başlat, PCM event'lerini bu id ile etiketle
+/// 3. `pause(id)` / `resume(id)` → Stream'i yıkmadan emit'i durdur/devam ettir
+/// 4. `stop(id)` → Thread'e dur sinyali ver, handle'ı haritadan çıkar
+///
+/// Thread kendi kendine de biterse (emit hatası, WASAPI/cpal stream hatası)
+/// aynı şekilde kendi handle'ını haritadan çıkarır — `stop(id)` hiç
+/// çağrılmasa bile ölü oturumlar `handles`'ta birikmez, `pause`/`resume`
+/// böyle bir id için sessizce "başarılı" dönmek yerine gerçek bir hata verir.
+pub struct CaptureManager {
+    next_id: AtomicU64,
+    handles: Arc<Mutex<HashMap<CaptureId, CaptureHandle>>>,
 }
 
-impl AudioCapture {
+impl CaptureManager {
     pub fn new() -> Self {
         Self {
-            running: Arc::new(AtomicBool::new(false)),
+            next_id: AtomicU64::new(1),
+            handles: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
-    /// Capture'ın şu an çalışıp çalışmadığını kontrol eder.
-    #[allow(dead_code)]
-    pub fn is_running(&self) -> bool {
-        self.running.load(Ordering::SeqCst)
-    }
-
-    /// Background thread'deki capture loop'u durdurur.
+    /// Bir sonraki `CaptureId`'yi üretir.
     ///
-    /// AtomicBool flag false yapılır → capture thread bir sonraki
-    /// iterasyonda flag'i kontrol edip temiz bir şekilde çıkar.
-    /// Thread join yapmaz — fire-and-forget. Thread kendi kendine kapanır.
-    pub fn stop(&self) {
-        self.running.store(false, Ordering::SeqCst);
+    /// `u64` sayaç pratikte asla taşmaz, ama taşma olursa panic yerine
+    /// açıklayıcı bir hata döneriz — çağıran taraf (Tauri command) bunu
+    /// frontend'e toast olarak gösterebilir.
+    fn allocate_id(&self) -> Result<CaptureId, String> {
+        let id = self
+            .next_id
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_add(1))
+            .map_err(|_| "Capture id counter overflowed".to_string())?;
+        Ok(CaptureId(id))
     }
 
-    /// WASAPI per-process audio capture başlatır.
+    /// Per-process / loopback audio capture başlatır, yeni bir `CaptureId` döner.
+    ///
+    /// `pid` + `include`, EXCLUDE ile INCLUDE mode arasında seçim yapar:
+    /// - `include = false` (EXCLUDE): `pid`'nin process tree'si HARİÇ tüm
+    ///   sistem sesi yakalanır. `start_audio_capture` komutu bunu kendi
+    ///   PID'imizle kullanır (echo önleme).
+    /// - `include = true` (INCLUDE): SADECE `pid`'nin process tree'sinin
+    ///   sesi yakalanır. `start_audio_capture_for_pid` komutu bunu seçilen
+    ///   bir uygulamanın sesini paylaşmak için kullanır.
     ///
-    /// Background thread'de çalışır:
-    /// 1. COM init (MTA — WASAPI gerektirir)
-    /// 2. AudioClient oluştur (EXCLUDE mode, kendi PID'imiz)
-    /// 3. 48kHz stereo f32 capture → i16 dönüşüm → IPC emit
+    /// Önceki tekil-oturum tasarımının aksine, zaten çalışan oturumlar bu
+    /// çağrıyı engellemez — her çağrı kendi bağımsız `CaptureId`'sini alır.
     ///
-    /// Hata durumları:
-    /// - Zaten çalışıyorsa: "Audio capture already running"
-    /// - Windows dışı platform: "Per-process audio capture is only supported on Windows"
-    /// - WASAPI hatası (eski Windows, cihaz yok): thread log'layıp kapanır
+    /// `config` negotiation'ı baştan reddeder: `CaptureConfig::validate()`
+    /// başarısız olursa thread hiç başlatılmaz, açıklayıcı hata döner —
+    /// temsil edilemeyen bir rate/kanal sessizce bozuk ses üretmez.
     #[cfg(windows)]
-    pub fn start(&self, app: AppHandle) -> Result<(), String> {
-        // Çift başlatma koruması
-        if self.running.load(Ordering::SeqCst) {
-            return Err("Audio capture already running".into());
-        }
+    pub fn start(
+        &self,
+        app: AppHandle,
+        pid: u32,
+        include: bool,
+        config: CaptureConfig,
+    ) -> Result<CaptureId, String> {
+        config.validate()?;
+        let id = self.allocate_id()?;
+        let running = Arc::new(AtomicBool::new(true));
+        let paused = Arc::new(AtomicBool::new(false));
 
-        self.running.store(true, Ordering::SeqCst);
-        let running = self.running.clone();
+        // Handle'ı thread'i başlatmadan ÖNCE kaydediyoruz — aksi halde thread
+        // anında hata ile bitip kendi kendini haritadan silmeye çalışırsa, bu
+        // silme burada insert edilecek kaydı henüz hiç var olmadan kaçırır ve
+        // ölü handle sonsuza kadar haritada kalır.
+        self.handles
+            .lock()
+            .map_err(|e| format!("State lock failed: {}", e))?
+            .insert(
+                id,
+                CaptureHandle {
+                    running: running.clone(),
+                    paused: paused.clone(),
+                },
+            );
 
+        let thread_handles = self.handles.clone();
         // Background thread: WASAPI capture loop
         // AudioCaptureClient !Send ve !Sync olduğu için tüm WASAPI işlemleri
-        // bu thread içinde kalmalı. Sadece AtomicBool (running) ve AppHandle
+        // bu thread içinde kalmalı. Sadece AtomicBool'lar ve AppHandle
         // (Send + Sync) thread'ler arası paylaşılır.
         std::thread::spawn(move || {
-            if let Err(e) = capture_loop(app, running.clone()) {
-                eprintln!("[AudioCapture] Capture loop error: {}", e);
+            if let Err(e) = wasapi_backend::capture_loop(
+                app,
+                running.clone(),
+                paused,
+                pid,
+                include,
+                id,
+                config,
+            ) {
+                eprintln!("[AudioCapture] Capture loop error ({:?}): {}", id, e);
             }
             // Thread bittiğinde flag'i temizle — is_running() false dönecek
             running.store(false, Ordering::SeqCst);
+            // Thread kendi kendine bittiyse (emit/packet hatası) handle'ı
+            // haritadan çıkar — aksi halde `stop(id)` hiç çağrılmadığı sürece
+            // ölü oturum haritada kalır ve `pause`/`resume` ona sessizce
+            // "başarılı" döner.
+            if let Ok(mut handles) = thread_handles.lock() {
+                handles.remove(&id);
+            }
         });
 
-        Ok(())
+        Ok(id)
     }
 
-    /// Non-Windows: per-process audio capture desteklenmiyor.
-    /// Frontend bu hatayı alınca sessizce devam eder (sadece video paylaşılır).
+    /// macOS/Linux: `cpal` loopback/monitor backend üzerinden capture başlatır.
+    ///
+    /// `pid`/`include` bu platformda desteklenmez — cpal loopback/monitor
+    /// device'lar per-process ayrım yapamaz, her zaman sink'e giden her
+    /// şeyi yakalar. Parametreler sadece Windows ile aynı komut imzasını
+    /// korumak için alınır ve yok sayılır.
     #[cfg(not(windows))]
-    pub fn start(&self, _app: AppHandle) -> Result<(), String> {
-        Err("Per-process audio capture is only supported on Windows".into())
+    pub fn start(
+        &self,
+        app: AppHandle,
+        _pid: u32,
+        _include: bool,
+        config: CaptureConfig,
+    ) -> Result<CaptureId, String> {
+        config.validate()?;
+        let id = self.allocate_id()?;
+        let running = Arc::new(AtomicBool::new(true));
+        let paused = Arc::new(AtomicBool::new(false));
+
+        // Handle'ı thread'i başlatmadan ÖNCE kaydediyoruz — bkz. Windows
+        // varyantındaki aynı isimli not.
+        self.handles
+            .lock()
+            .map_err(|e| format!("State lock failed: {}", e))?
+            .insert(
+                id,
+                CaptureHandle {
+                    running: running.clone(),
+                    paused: paused.clone(),
+                },
+            );
+
+        let thread_handles = self.handles.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = cpal_backend::capture_loop(app, running.clone(), paused, id, config) {
+                eprintln!("[AudioCapture] Capture loop error ({:?}): {}", id, e);
+            }
+            running.store(false, Ordering::SeqCst);
+            // Thread kendi kendine bittiyse handle'ı haritadan çıkar —
+            // bkz. Windows varyantındaki aynı isimli not.
+            if let Ok(mut handles) = thread_handles.lock() {
+                handles.remove(&id);
+            }
+        });
+
+        Ok(id)
     }
+
+    /// `id`'ye ait capture oturumunu durdurur ve manager'dan çıkarır.
+    ///
+    /// AtomicBool flag false yapılır → capture thread bir sonraki
+    /// iterasyonda flag'i kontrol edip temiz bir şekilde çıkar.
+    /// Thread join yapmaz — fire-and-forget. Thread kendi kendine kapanır.
+    /// `id` bulunamazsa (zaten durmuş/hiç var olmamış) sessizce başarılı döner.
+    pub fn stop(&self, id: CaptureId) -> Result<(), String> {
+        if let Some(handle) = self
+            .handles
+            .lock()
+            .map_err(|e| format!("State lock failed: {}", e))?
+            .remove(&id)
+        {
+            handle.running.store(false, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+
+    /// `id`'ye ait oturumda emit'i durdurur, WASAPI/cpal stream'i canlı tutar.
+    ///
+    /// Capture loop çalışmaya devam eder (buffer drene edilir, stream
+    /// teardown olmaz) ama `paused` iken hiçbir `PcmChunk` emit edilmez —
+    /// bu sayede `resume()` yeniden stream kurmadan anında devam edebilir.
+    pub fn pause(&self, id: CaptureId) -> Result<(), String> {
+        let handles = self
+            .handles
+            .lock()
+            .map_err(|e| format!("State lock failed: {}", e))?;
+        let handle = handles
+            .get(&id)
+            .ok_or_else(|| "No such capture session".to_string())?;
+        handle.paused.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// `id`'ye ait oturumda emit'i devam ettirir.
+    pub fn resume(&self, id: CaptureId) -> Result<(), String> {
+        let handles = self
+            .handles
+            .lock()
+            .map_err(|e| format!("State lock failed: {}", e))?;
+        let handle = handles
+            .get(&id)
+            .ok_or_else(|| "No such capture session".to_string())?;
+        handle.paused.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+/// Aktif bir Windows audio session'ını tanımlar.
+///
+/// `list_audio_sessions()` komutu ile frontend'e döner — kullanıcı bu
+/// listeden bir PID seçip `start_audio_capture_for_pid` ile INCLUDE mode
+/// capture başlatabilir (raw PID bilmesine gerek kalmaz).
+#[derive(Clone, serde::Serialize)]
+pub struct AudioSessionInfo {
+    /// Session'ı oluşturan process'in PID'i.
+    pub pid: u32,
+    /// Kullanıcıya gösterilecek isim — session display name varsa o,
+    /// yoksa process'in exe dosya adı.
+    pub display_name: String,
+}
+
+/// Şu an ses çalan/çalmış aktif audio session'larını listeler.
+///
+/// Windows'ta IAudioSessionManager2 üzerinden varsayılan render cihazının
+/// session'ları enumerate edilir. macOS/Linux'ta bu kavramın eşdeğeri yok —
+/// hata döner, frontend PID seçici UI'ı göstermez.
+#[cfg(windows)]
+pub fn list_audio_sessions() -> Result<Vec<AudioSessionInfo>, String> {
+    wasapi_backend::list_audio_sessions()
+}
+
+#[cfg(not(windows))]
+pub fn list_audio_sessions() -> Result<Vec<AudioSessionInfo>, String> {
+    Err("Audio session enumeration is only supported on Windows".into())
+}
+
+/// f32 PCM byte dizisini i16 sample dizisine dönüştürür.
+///
+/// Hem WASAPI hem cpal backend'i bu fonksiyonu paylaşır — platformdan
+/// bağımsız, saf veri dönüşümü.
+///
+/// f32 sample'lar [-1.0, 1.0] aralığında (teorik). Pratikte bazı ses
+/// kaynakları bu aralığı aşabilir — clamp ile sınırlarız.
+///
+/// Dönüşüm formülü: i16_sample = clamp(f32_sample, -1.0, 1.0) × 32767
+/// i16 aralığı: [-32768, 32767], biz 32767 kullanarak simetrik tutuyoruz.
+fn f32_samples_to_i16(samples: &[f32]) -> Vec<i16> {
+    samples
+        .iter()
+        .map(|&sample| {
+            let clamped = sample.clamp(-1.0, 1.0);
+            (clamped * 32767.0) as i16
+        })
+        .collect()
+}
+
+/// Little-endian f32 byte dizisini i16 sample dizisine dönüştürür.
+///
+/// Byte order: Little-endian (Windows her zaman LE kullanır).
+/// Her 4 byte = 1 f32 sample → 1 i16 sample.
+#[cfg(windows)]
+fn f32_bytes_to_i16(bytes: &[u8]) -> Vec<i16> {
+    let samples: Vec<f32> = bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect();
+    f32_samples_to_i16(&samples)
 }
 
 // ═══════════════════════════════════════════════════════════════════════
 // Windows-only: WASAPI capture loop implementation
 // ═══════════════════════════════════════════════════════════════════════
 
-/// Ana capture döngüsü — background thread'de çalışır.
-///
-/// WASAPI event-driven model:
-/// 1. Event bekle (WASAPI yeni buffer hazır olduğunda sinyal verir)
-/// 2. Tüm mevcut paketleri oku (VecDeque'ye biriktir)
-/// 3. 20ms'lik chunk'lara böl
-/// 4. f32 → i16 dönüşümü yap
-/// 5. Tauri IPC event ile frontend'e gönder
-/// 6. running flag false olana kadar tekrarla
 #[cfg(windows)]
-fn capture_loop(app: AppHandle, running: Arc<AtomicBool>) -> Result<(), String> {
-    use std::collections::VecDeque;
-    use tauri::Emitter;
-    use wasapi::*;
-
-    // ─── 1. COM Başlat ───
-    // WASAPI, COM (Component Object Model) üzerine kurulu bir Windows API'si.
-    // Her thread'de COM'un başlatılması gerekir.
-    // MTA = Multi-Threaded Apartment: birden fazla thread aynı COM nesnelerine
-    // erişebilir. UI thread'lerde STA kullanılır, background'da MTA tercih edilir.
-    // initialize_mta() → CoInitializeEx(None, COINIT_MULTITHREADED) çağırır.
-    // Dönüş tipi HRESULT — .ok() ile Result'a çevrilir.
-    initialize_mta()
-        .ok()
-        .map_err(|e| format!("COM initialization failed: {}", e))?;
-
-    // ─── 2. Kendi PID'imizi al ───
-    // EXCLUDE mode'da bu PID'e ait process tree'nin tüm ses çıkışı
-    // yakalama dışında bırakılır. Tauri uygulaması tek process tree'de çalışır
-    // (ana process + WebView child process'leri), dolayısıyla WebView'den
-    // çıkan voice chat sesi capture'a girmez.
-    let pid = std::process::id();
-
-    // ─── 3. Application Loopback Client Oluştur ───
-    // new_application_loopback_client: Windows 10 Build 20348+ API'si.
-    // Dahili olarak ActivateAudioInterfaceAsync + VIRTUAL_AUDIO_DEVICE_PROCESS_LOOPBACK
-    // kullanır.
-    //
-    // include_tree parametresi:
-    //   true  → INCLUDE mode: sadece hedef process tree'nin sesini yakala
-    //   false → EXCLUDE mode: hedef process tree HARİÇ tüm sistem sesini yakala
-    //
-    // Biz false (EXCLUDE) kullanıyoruz: "Benim uygulamamın sesi HARİÇ, her şeyi yakala"
-    // → Oyun sesi, müzik, diğer uygulamalar yakalanır
-    // → Voice chat sesi (kendi WebView'umuz) yakalanMAZ
-    let mut audio_client = AudioClient::new_application_loopback_client(pid, false)
-        .map_err(|e| format!("Failed to create loopback client: {}", e))?;
-
-    // ─── 4. Wave Format Tanımla ───
-    // WASAPI shared mode doğal olarak f32 (IEEE 754 Float) kullanır.
-    // Biz de f32 olarak capture edip, IPC öncesinde i16'ya dönüştüreceğiz.
-    //
-    // WaveFormat parametreleri:
-    //   storebits: 32    — her sample 4 byte depolama
-    //   validbits: 32    — 32 bit'in tamamı geçerli
-    //   SampleType::Float — IEEE 754 float format
-    //   48000            — WebRTC/LiveKit standart sample rate
-    //   2                — stereo (sol + sağ kanal)
-    //   None             — channel mask otomatik (SPEAKER_FRONT_LEFT | SPEAKER_FRONT_RIGHT)
-    let desired_format = WaveFormat::new(32, 32, &SampleType::Float, 48000, 2, None);
-
-    // blockalign: bir frame'in byte boyutu = channels × bytes_per_sample
-    // f32 stereo: 2 kanal × 4 byte = 8 byte/frame
-    let blockalign = desired_format.get_blockalign() as usize;
-
-    // ─── 5. Stream Mode Ayarla ───
-    // EventsShared: Event-driven capture modu.
-    // WASAPI yeni buffer hazır olduğunda Windows Event nesnesi sinyal eder,
-    // biz wait_for_event() ile bekleriz. Polling'e göre çok daha CPU dostu —
-    // boş döngü yapmaz, thread sleep durumunda kalır.
-    //
-    // autoconvert: true
-    //   WASAPI audio engine, cihazın gerçek formatı (genellikle f32 44.1/48kHz)
-    //   ile bizim istediğimiz format arasında otomatik dönüşüm yapar.
-    //   Bu sayede cihaz formatı ne olursa olsun bize 48kHz f32 stereo gelir.
-    //
-    // buffer_duration_hns: 0
-    //   Application loopback client'lar için bu değer anlamsız — WASAPI
-    //   buffer boyutunu kendisi belirler. Dokümantasyon: "use 0".
-    let mode = StreamMode::EventsShared {
-        autoconvert: true,
-        buffer_duration_hns: 0,
-    };
-
-    // ─── 6. Client'ı Başlat ───
-    // Direction::Capture: Ses yakalama modu (Render = ses çalma)
-    // Application loopback için MUTLAKA Capture kullanılmalı —
-    // Render kullanmak RenderToCaptureDevice hatası verir.
-    audio_client
-        .initialize_client(&desired_format, &Direction::Capture, &mode)
-        .map_err(|e| format!("Client initialization failed: {}", e))?;
-
-    // ─── 7. Event Handle Al ───
-    // set_get_eventhandle(): Windows Event nesnesi oluşturur ve WASAPI'ye kaydeder.
-    // WASAPI yeni buffer hazır olduğunda bu handle'ı SignalEvent ile sinyal eder.
-    // wait_for_event(ms): WaitForSingleObject ile bloklar — CPU kullanmaz.
-    // Handle Drop trait implemente eder — scope dışına çıkınca otomatik kapanır.
-    let h_event = audio_client
-        .set_get_eventhandle()
-        .map_err(|e| format!("Failed to get event handle: {}", e))?;
-
-    // ─── 8. Capture Alt-Client Al ───
-    // AudioCaptureClient: IAudioCaptureClient wrapper'ı.
-    // Buffer'dan ses verisi okumak için kullanılır.
-    //
-    // ÖNEMLİ: !Send ve !Sync trait'leri implemente ETMEZ.
-    // Sadece oluşturulduğu thread'de kullanılabilir — bu yüzden tüm
-    // WASAPI işlemleri tek thread'de (bu background thread) yapılır.
-    let capture_client = audio_client
-        .get_audiocaptureclient()
-        .map_err(|e| format!("Failed to get capture client: {}", e))?;
-
-    // ─── 9. Buffer Hazırla ───
-    // VecDeque<u8>: çift taraflı kuyruk (double-ended queue).
-    // Arkadan ekleme (push_back via read_from_device_to_deque) ve
-    // önden çıkarma (drain) O(1) amortize.
-    //
-    // Neden Vec değil VecDeque?
-    // Vec ile önden eleman çıkarmak O(n) (tüm elemanlar kaydırılır).
-    // VecDeque ring buffer — head/tail pointer'ları ile O(1) drain.
-    // Ses verisi sürekli arkadan eklenir, önden chunk'lar halinde çıkarılır
-    // → VecDeque ideal veri yapısı.
-    let mut sample_queue: VecDeque<u8> = VecDeque::new();
-
-    // 20ms chunk boyutu hesaplama:
-    // 48000 Hz × 0.020 s = 960 frame (20ms'lik ses)
-    // 960 frame × 8 byte/frame (f32 stereo) = 7680 byte
-    // Dönüşüm sonrası: 960 frame × 4 byte/frame (i16 stereo) = 3840 byte = 1920 i16
-    let chunk_frames: usize = 960;
-    let chunk_bytes = chunk_frames * blockalign;
-
-    // ─── 10. Stream Başlat ───
-    // start_stream(): WASAPI capture'ı aktif eder.
-    // Bu noktadan itibaren sistem sesi buffer'a akmaya başlar.
-    audio_client
-        .start_stream()
-        .map_err(|e| format!("Failed to start audio stream: {}", e))?;
-
-    // ─── 11. Capture Loop ───
-    // Ana döngü: Event bekle → buffer oku → chunk'la → dönüştür → IPC emit
-    //
-    // Döngü şu durumlarda biter:
-    // - running flag false (stop() çağrıldı)
-    // - WASAPI okuma hatası (cihaz kayboldu vb.)
-    // - IPC emit hatası (window kapandı, listener yok)
-    while running.load(Ordering::SeqCst) {
-        // 11a. Event bekle (100ms timeout)
-        // Kısa timeout sayesinde running flag kontrolü responsive olur.
-        // stop() çağrıldıktan sonra en fazla 100ms içinde döngü biter.
-        // Timeout: hedef process ses üretmiyor demek — döngü başına dön.
-        if h_event.wait_for_event(100).is_err() {
-            continue;
-        }
+mod wasapi_backend {
+    use super::{f32_bytes_to_i16, CaptureConfig, CaptureId, PcmChunk};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use tauri::AppHandle;
+
+    /// Ana capture döngüsü — background thread'de çalışır.
+    ///
+    /// WASAPI event-driven model:
+    /// 1. Event bekle (WASAPI yeni buffer hazır olduğunda sinyal verir)
+    /// 2. Tüm mevcut paketleri oku (VecDeque'ye biriktir)
+    /// 3. `config.chunk_ms`'lik chunk'lara böl
+    /// 4. f32 → i16 dönüşümü yap
+    /// 5. `paused` değilse Tauri IPC event ile frontend'e gönder
+    /// 6. running flag false olana kadar tekrarla
+    ///
+    /// `paused` true iken buffer'ı okumaya devam ederiz (stream teardown
+    /// olmaz) ama emit atlanır — `resume()` bu yüzden stream'i yeniden
+    /// kurmadan anında devam edebilir.
+    pub fn capture_loop(
+        app: AppHandle,
+        running: Arc<AtomicBool>,
+        paused: Arc<AtomicBool>,
+        pid: u32,
+        include: bool,
+        id: CaptureId,
+        config: CaptureConfig,
+    ) -> Result<(), String> {
+        use std::collections::VecDeque;
+        use tauri::Emitter;
+        use wasapi::*;
+
+        // ─── 1. COM Başlat ───
+        // WASAPI, COM (Component Object Model) üzerine kurulu bir Windows API'si.
+        // Her thread'de COM'un başlatılması gerekir.
+        // MTA = Multi-Threaded Apartment: birden fazla thread aynı COM nesnelerine
+        // erişebilir. UI thread'lerde STA kullanılır, background'da MTA tercih edilir.
+        // initialize_mta() → CoInitializeEx(None, COINIT_MULTITHREADED) çağırır.
+        // Dönüş tipi HRESULT — .ok() ile Result'a çevrilir.
+        initialize_mta()
+            .ok()
+            .map_err(|e| format!("COM initialization failed: {}", e))?;
+
+        // ─── 2. Hedef PID ve Mod ───
+        // EXCLUDE mode'da (include=false) `pid`'nin process tree'sinin tüm ses
+        // çıkışı yakalama dışında bırakılır — `start_audio_capture` komutu bunu
+        // kendi PID'imizle çağırır (Tauri uygulaması tek process tree'de çalışır,
+        // WebView'den çıkan voice chat sesi capture'a girmez).
+        // INCLUDE mode'da (include=true) SADECE `pid`'nin sesi yakalanır —
+        // `start_audio_capture_for_pid` komutu seçilen bir uygulamayı hedefler.
+
+        // ─── 3. Application Loopback Client Oluştur ───
+        // new_application_loopback_client: Windows 10 Build 20348+ API'si.
+        // Dahili olarak ActivateAudioInterfaceAsync + VIRTUAL_AUDIO_DEVICE_PROCESS_LOOPBACK
+        // kullanır.
+        //
+        // include_tree parametresi:
+        //   true  → INCLUDE mode: sadece hedef process tree'nin sesini yakala
+        //   false → EXCLUDE mode: hedef process tree HARİÇ tüm sistem sesini yakala
+        //
+        // `include` çağıran tarafın isteğine göre belirlenir:
+        // - `start_audio_capture` kendi PID'imizle false (EXCLUDE) geçer:
+        //   "Benim uygulamamın sesi HARİÇ, her şeyi yakala" → oyun sesi, müzik,
+        //   diğer uygulamalar yakalanır, voice chat sesi (kendi WebView'umuz) yakalanMAZ.
+        // - `start_audio_capture_for_pid` seçilen bir PID ile true (INCLUDE) geçer:
+        //   "SADECE bu uygulamanın sesini yakala" → örn. tek bir oyun/sekme paylaşımı.
+        let mut audio_client = AudioClient::new_application_loopback_client(pid, include)
+            .map_err(|e| format!("Failed to create loopback client: {}", e))?;
+
+        // ─── 4. Wave Format Tanımla ───
+        // WASAPI shared mode doğal olarak f32 (IEEE 754 Float) kullanır.
+        // Biz de f32 olarak capture edip, IPC öncesinde i16'ya dönüştüreceğiz.
+        //
+        // WaveFormat parametreleri:
+        //   storebits: 32          — her sample 4 byte depolama
+        //   validbits: 32          — 32 bit'in tamamı geçerli
+        //   SampleType::Float      — IEEE 754 float format
+        //   config.sample_rate     — çağıranın istediği sample rate
+        //   config.channels        — çağıranın istediği kanal sayısı
+        //   None                   — channel mask otomatik
+        //
+        // `autoconvert: true` (aşağıda) sayesinde cihazın native formatı ne
+        // olursa olsun WASAPI bize burada istenen formatı üretir.
+        let desired_format =
+            WaveFormat::new(32, 32, &SampleType::Float, config.sample_rate as usize, config.channels as usize, None);
+
+        // blockalign: bir frame'in byte boyutu = channels × bytes_per_sample
+        let blockalign = desired_format.get_blockalign() as usize;
+        debug_assert_eq!(blockalign, config.blockalign());
 
-        // 11b. Mevcut tüm paketleri oku
-        // WASAPI birden fazla paket biriktirmiş olabilir — hepsini drain et.
-        // get_next_packet_size(): sonraki paketin frame sayısı.
-        // None veya Some(0) = veri yok, iç döngüden çık.
-        loop {
-            match capture_client.get_next_packet_size() {
-                Ok(Some(0)) | Ok(None) => break,
-                Ok(Some(_frames)) => {
-                    // read_from_device_to_deque: paketin tüm byte'larını VecDeque'ye ekler.
-                    // Dahili olarak IAudioCaptureClient::GetBuffer + ReleaseBuffer çağırır.
-                    // Değişken uzunluklu okuma — VecDeque dinamik büyür.
-                    if let Err(e) = capture_client.read_from_device_to_deque(&mut sample_queue) {
-                        eprintln!("[AudioCapture] Buffer read error: {}", e);
+        // ─── 5. Stream Mode Ayarla ───
+        // EventsShared: Event-driven capture modu.
+        // WASAPI yeni buffer hazır olduğunda Windows Event nesnesi sinyal eder,
+        // biz wait_for_event() ile bekleriz. Polling'e göre çok daha CPU dostu —
+        // boş döngü yapmaz, thread sleep durumunda kalır.
+        //
+        // autoconvert: true
+        //   WASAPI audio engine, cihazın gerçek formatı (genellikle f32 44.1/48kHz)
+        //   ile bizim istediğimiz format arasında otomatik dönüşüm yapar.
+        //   Bu sayede cihaz formatı ne olursa olsun bize `config`'te istenen
+        //   sample rate/kanal sayısında f32 gelir.
+        //
+        // buffer_duration_hns: 0
+        //   Application loopback client'lar için bu değer anlamsız — WASAPI
+        //   buffer boyutunu kendisi belirler. Dokümantasyon: "use 0".
+        let mode = StreamMode::EventsShared {
+            autoconvert: true,
+            buffer_duration_hns: 0,
+        };
+
+        // ─── 6. Client'ı Başlat ───
+        // Direction::Capture: Ses yakalama modu (Render = ses çalma)
+        // Application loopback için MUTLAKA Capture kullanılmalı —
+        // Render kullanmak RenderToCaptureDevice hatası verir.
+        audio_client
+            .initialize_client(&desired_format, &Direction::Capture, &mode)
+            .map_err(|e| format!("Client initialization failed: {}", e))?;
+
+        // ─── 7. Event Handle Al ───
+        // set_get_eventhandle(): Windows Event nesnesi oluşturur ve WASAPI'ye kaydeder.
+        // WASAPI yeni buffer hazır olduğunda bu handle'ı SignalEvent ile sinyal eder.
+        // wait_for_event(ms): WaitForSingleObject ile bloklar — CPU kullanmaz.
+        // Handle Drop trait implemente eder — scope dışına çıkınca otomatik kapanır.
+        let h_event = audio_client
+            .set_get_eventhandle()
+            .map_err(|e| format!("Failed to get event handle: {}", e))?;
+
+        // ─── 8. Capture Alt-Client Al ───
+        // AudioCaptureClient: IAudioCaptureClient wrapper'ı.
+        // Buffer'dan ses verisi okumak için kullanılır.
+        //
+        // ÖNEMLİ: !Send ve !Sync trait'leri implemente ETMEZ.
+        // Sadece oluşturulduğu thread'de kullanılabilir — bu yüzden tüm
+        // WASAPI işlemleri tek thread'de (bu background thread) yapılır.
+        let capture_client = audio_client
+            .get_audiocaptureclient()
+            .map_err(|e| format!("Failed to get capture client: {}", e))?;
+
+        // ─── 9. Buffer Hazırla ───
+        // VecDeque<u8>: çift taraflı kuyruk (double-ended queue).
+        // Arkadan ekleme (push_back via read_from_device_to_deque) ve
+        // önden çıkarma (drain) O(1) amortize.
+        let mut sample_queue: VecDeque<u8> = VecDeque::new();
+
+        // Chunk boyutu, sabit 960 frame/20ms yerine config'ten türetilir:
+        // `sample_rate * chunk_ms / 1000` frame × `blockalign` byte/frame.
+        // Örn. varsayılan 48kHz/20ms: 960 frame × 8 byte/frame (f32 stereo) = 7680 byte.
+        let chunk_frames = config.chunk_frames();
+        let chunk_bytes = chunk_frames * blockalign;
+
+        // ─── 10. Stream Başlat ───
+        // start_stream(): WASAPI capture'ı aktif eder.
+        // Bu noktadan itibaren sistem sesi buffer'a akmaya başlar.
+        audio_client
+            .start_stream()
+            .map_err(|e| format!("Failed to start audio stream: {}", e))?;
+
+        // ─── 11. Capture Loop ───
+        // Ana döngü: Event bekle → buffer oku → chunk'la → dönüştür → IPC emit
+        //
+        // Döngü şu durumlarda biter:
+        // - running flag false (stop() çağrıldı)
+        // - WASAPI okuma hatası (cihaz kayboldu vb.)
+        // - IPC emit hatası (window kapandı, listener yok)
+        while running.load(Ordering::SeqCst) {
+            // 11a. Event bekle (100ms timeout)
+            // Kısa timeout sayesinde running flag kontrolü responsive olur.
+            // stop() çağrıldıktan sonra en fazla 100ms içinde döngü biter.
+            // Timeout: hedef process ses üretmiyor demek — döngü başına dön.
+            if h_event.wait_for_event(100).is_err() {
+                continue;
+            }
+
+            // 11b. Mevcut tüm paketleri oku
+            // WASAPI birden fazla paket biriktirmiş olabilir — hepsini drain et.
+            loop {
+                match capture_client.get_next_packet_size() {
+                    Ok(Some(0)) | Ok(None) => break,
+                    Ok(Some(_frames)) => {
+                        if let Err(e) = capture_client.read_from_device_to_deque(&mut sample_queue)
+                        {
+                            eprintln!("[AudioCapture] Buffer read error: {}", e);
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        // Kritik hata — capture devam edemez
+                        eprintln!("[AudioCapture] Packet size query failed: {}", e);
+                        running.store(false, Ordering::SeqCst);
                         break;
                     }
                 }
-                Err(e) => {
-                    // Kritik hata — capture devam edemez
-                    eprintln!("[AudioCapture] Packet size query failed: {}", e);
+            }
+
+            // 11c. Biriken verileri `config.chunk_ms`'lik chunk'lara böl ve IPC ile gönder
+            while sample_queue.len() >= chunk_bytes {
+                let chunk: Vec<u8> = sample_queue.drain(..chunk_bytes).collect();
+
+                // Duraklatılmışken de buffer'ı drene ediyoruz (birikip
+                // taşmasın diye) ama emit atlıyoruz.
+                if paused.load(Ordering::SeqCst) {
+                    continue;
+                }
+
+                let i16_samples = f32_bytes_to_i16(&chunk);
+                if app
+                    .emit(
+                        "audio-pcm",
+                        PcmChunk {
+                            id,
+                            sample_rate: config.sample_rate,
+                            channels: config.channels,
+                            samples: i16_samples,
+                        },
+                    )
+                    .is_err()
+                {
+                    // emit hatası: tüm window'lar kapandı veya app shutdown
                     running.store(false, Ordering::SeqCst);
                     break;
                 }
             }
         }
 
-        // 11c. Biriken verileri 20ms chunk'lara böl ve IPC ile gönder
-        // chunk_bytes (7680) kadar veri biriktiyse: çıkar, dönüştür, emit et.
-        // Birden fazla chunk birikmiş olabilir — while ile hepsini gönder.
-        while sample_queue.len() >= chunk_bytes {
-            // VecDeque drain: ilk chunk_bytes byte'ı çıkar
-            // drain(..n): O(n) — pop_front döngüsünden daha verimli,
-            // tek seferde range çıkarır.
-            let chunk: Vec<u8> = sample_queue.drain(..chunk_bytes).collect();
-
-            // f32 PCM → i16 PCM dönüşümü
-            // Her 4 byte (f32) → 1 i16 sample
-            // 7680 byte → 1920 i16 sample (960 frame × 2 kanal)
-            let i16_samples = f32_bytes_to_i16(&chunk);
-
-            // Tauri IPC event: "audio-pcm"
-            // Frontend listen("audio-pcm", callback) ile alır.
-            // PcmChunk { samples } serde ile JSON'a serialize edilir.
-            if app
-                .emit("audio-pcm", PcmChunk { samples: i16_samples })
-                .is_err()
-            {
-                // emit hatası: tüm window'lar kapandı veya app shutdown
-                running.store(false, Ordering::SeqCst);
-                break;
+        // ─── 12. Temiz Kapanış ───
+        // stop_stream(): WASAPI capture'ı durdurur, buffer'ları temizler.
+        // Hata yutulur — zaten kapatıyoruz, hata loglamak anlamsız.
+        let _ = audio_client.stop_stream();
+
+        Ok(())
+    }
+
+    /// Aktif audio session'larını enumerate eder.
+    ///
+    /// Windows audio session API zinciri:
+    /// IMMDeviceEnumerator → GetDefaultAudioEndpoint(Render, Console)
+    ///   → IMMDevice → Activate::<IAudioSessionManager2>()
+    ///   → GetSessionEnumerator() → IAudioSessionControl → IAudioSessionControl2
+    ///   → GetProcessId()
+    ///
+    /// Display name önceliği:
+    /// 1. `IAudioSessionControl::GetDisplayName()` doluysa onu kullan
+    ///    (bazı uygulamalar kendi session adını set eder, ör. "Spotify").
+    /// 2. Boşsa, PID'den process exe adını (uzantısız) kullan.
+    ///
+    /// `_Total` session'ı (PID 0, sistem sesleri toplamı) listeye dahil edilmez —
+    /// frontend'in picker'ında gerçek bir uygulama karşılığı yoktur.
+    pub fn list_audio_sessions() -> Result<Vec<super::AudioSessionInfo>, String> {
+        use windows::Win32::Media::Audio::{
+            eConsole, eRender, IAudioSessionControl2, IAudioSessionManager2, IMMDeviceEnumerator,
+            MMDeviceEnumerator,
+        };
+        use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_ALL};
+
+        // initialize_mta zaten capture_loop tarafında çağrılmış olabilir ama
+        // bu fonksiyon bağımsız da çağrılabildiği için kendi COM init'ini yapar.
+        let _ = wasapi::initialize_mta();
+
+        let sessions = unsafe {
+            let enumerator: IMMDeviceEnumerator =
+                CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)
+                    .map_err(|e| format!("Failed to create device enumerator: {}", e))?;
+            let device = enumerator
+                .GetDefaultAudioEndpoint(eRender, eConsole)
+                .map_err(|e| format!("Failed to get default render endpoint: {}", e))?;
+            let session_manager: IAudioSessionManager2 = device
+                .Activate(CLSCTX_ALL, None)
+                .map_err(|e| format!("Failed to activate session manager: {}", e))?;
+            let session_enum = session_manager
+                .GetSessionEnumerator()
+                .map_err(|e| format!("Failed to get session enumerator: {}", e))?;
+
+            let count = session_enum
+                .GetCount()
+                .map_err(|e| format!("Failed to get session count: {}", e))?;
+
+            let mut sessions = Vec::new();
+            for i in 0..count {
+                let Ok(control) = session_enum.GetSession(i) else {
+                    continue;
+                };
+                let Ok(control2): Result<IAudioSessionControl2, _> = control.cast() else {
+                    continue;
+                };
+                let Ok(pid) = control2.GetProcessId() else {
+                    continue;
+                };
+                if pid == 0 {
+                    // _Total session — gerçek bir process değil, atla.
+                    continue;
+                }
+
+                let display_name = control
+                    .GetDisplayName()
+                    .ok()
+                    .and_then(|s| (!s.is_empty()).then(|| s.to_string()))
+                    .unwrap_or_else(|| process_exe_name(pid));
+
+                sessions.push(super::AudioSessionInfo { pid, display_name });
             }
+            sessions
+        };
+
+        Ok(sessions)
+    }
+
+    /// `pid`'nin process exe adını (uzantısız, ör. "chrome") döner.
+    ///
+    /// Session'ın kendi display name'i yoksa bu fallback olarak kullanılır.
+    /// Process bulunamazsa ham PID'i string olarak döner.
+    fn process_exe_name(pid: u32) -> String {
+        use windows::Win32::Foundation::CloseHandle;
+        use windows::Win32::System::ProcessStatus::K32GetModuleBaseNameW;
+        use windows::Win32::System::Threading::{OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION};
+
+        // `K32GetModuleBaseNameW` tek başına `PROCESS_QUERY_LIMITED_INFORMATION`
+        // ile çalışır, `PROCESS_VM_READ` gerektirmez. Fazladan istemek
+        // `OpenProcess`'i korumalı/yükseltilmiş process'lerde gereksiz yere
+        // başarısız kılar — bu durumda session bir isim yerine ham PID'e düşer.
+        unsafe {
+            let Ok(handle) = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) else {
+                return pid.to_string();
+            };
+
+            let mut buf = [0u16; 260];
+            let len = K32GetModuleBaseNameW(handle, None, &mut buf);
+            let _ = CloseHandle(handle);
+
+            if len == 0 {
+                return pid.to_string();
+            }
+            String::from_utf16_lossy(&buf[..len as usize])
+                .trim_end_matches(".exe")
+                .to_string()
         }
     }
 
-    // ─── 12. Temiz Kapanış ───
-    // stop_stream(): WASAPI capture'ı durdurur, buffer'ları temizler.
-    // Hata yutulur — zaten kapatıyoruz, hata loglamak anlamsız.
-    let _ = audio_client.stop_stream();
+    /// Gerçek capture'ın açacağı process-loopback endpoint'inin desteklediği
+    /// (sample_rate, channels) kombinasyonlarını enumerate eder.
+    ///
+    /// `get_default_device(Direction::Capture)` ile asıl capture arasında bir
+    /// fark vardır: oradaki "varsayılan mikrofon" fiziksel/normal bir girdi
+    /// cihazıdır, ama `capture_loop` hiçbir zaman onu açmaz — her zaman
+    /// `new_application_loopback_client` ile kurulan SANAL process-loopback
+    /// cihazını açar. Bu iki endpoint farklı "native mix format"a ve farklı
+    /// `is_supported()` davranışına sahip olabilir; varsayılan mikrofonu
+    /// probe edip listeyi ona göre döndürmek, frontend'e gerçekte
+    /// honoring edilmeyecek formatlar önerebilir. Bu yüzden burada da aynı
+    /// `new_application_loopback_client` yolunu kullanıyoruz.
+    ///
+    /// `pid`/`include`, application-loopback'in açtığı sanal cihaz sınıfını
+    /// (dolayısıyla desteklenen format kümesini) etkilemez — sadece hangi
+    /// process ağacının sesinin capture'a dahil/hariç olacağını belirler.
+    /// Bu yüzden burada kendi PID'imizle (EXCLUDE mode, `start_audio_capture`
+    /// ile aynı varsayılan) temsilî bir client açmak yeterlidir; sonuç,
+    /// hangi pid/mode ile gerçek capture başlatılacağından bağımsızdır.
+    ///
+    /// Yaygın sample rate'leri (bant genişliği kısıtlı görüşmelerden müzik
+    /// kalitesine) ve mono/stereo'yu deneyip gerçekten kabul edilenleri
+    /// döneriz — cpal'in `supported_input_configs()`'inin WASAPI eşdeğeri.
+    pub fn query_supported_formats() -> Result<Vec<super::SupportedFormat>, String> {
+        let _ = wasapi::initialize_mta();
+
+        let audio_client =
+            wasapi::AudioClient::new_application_loopback_client(std::process::id(), false)
+                .map_err(|e| format!("Failed to create loopback client: {}", e))?;
 
-    Ok(())
+        let candidate_rates = [16_000u32, 24_000, 44_100, 48_000];
+        let candidate_channels = [1u16, 2];
+
+        let mut formats = Vec::new();
+        for &sample_rate in &candidate_rates {
+            for &channels in &candidate_channels {
+                let format = wasapi::WaveFormat::new(
+                    32,
+                    32,
+                    &wasapi::SampleType::Float,
+                    sample_rate as usize,
+                    channels as usize,
+                    None,
+                );
+                let mode = wasapi::StreamMode::EventsShared {
+                    autoconvert: true,
+                    buffer_duration_hns: 0,
+                };
+                if audio_client
+                    .is_supported(&format, &wasapi::Direction::Capture, &mode)
+                    .is_ok()
+                {
+                    formats.push(super::SupportedFormat {
+                        sample_rate,
+                        channels,
+                    });
+                }
+            }
+        }
+
+        Ok(formats)
+    }
 }
 
-/// f32 PCM byte dizisini i16 sample dizisine dönüştürür.
-///
-/// WASAPI f32 formatında [-1.0, 1.0] aralığında sample verir (teorik).
-/// Pratikte bazı ses kaynakları bu aralığı aşabilir — clamp ile sınırlarız.
-///
-/// Dönüşüm formülü: i16_sample = clamp(f32_sample, -1.0, 1.0) × 32767
-/// i16 aralığı: [-32768, 32767], biz 32767 kullanarak simetrik tutuyoruz.
-///
-/// Byte order: Little-endian (Windows her zaman LE kullanır).
-/// Her 4 byte = 1 f32 sample → 1 i16 sample.
-#[cfg(windows)]
-fn f32_bytes_to_i16(bytes: &[u8]) -> Vec<i16> {
-    bytes
-        .chunks_exact(4)
-        .map(|chunk| {
-            let sample = f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
-            let clamped = sample.clamp(-1.0, 1.0);
-            (clamped * 32767.0) as i16
-        })
-        .collect()
+// ═══════════════════════════════════════════════════════════════════════
+// macOS/Linux: cpal loopback/monitor capture loop implementation
+// ═══════════════════════════════════════════════════════════════════════
+
+#[cfg(not(windows))]
+mod cpal_backend {
+    use super::{f32_samples_to_i16, CaptureConfig, CaptureId, PcmChunk};
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tauri::{AppHandle, Emitter};
+
+    /// Loopback/monitor input cihazını bulur.
+    ///
+    /// Linux: PulseAudio/PipeWire, monitor source'ları normal input cihazı
+    /// olarak `cpal::Device::name()` içinde ".monitor" son ekiyle sunar —
+    /// `devices()` üzerinde isme göre arıyoruz.
+    ///
+    /// macOS: Kullanıcının önceden kurduğu bir aggregate/loopback cihazı
+    /// (ör. BlackHole, Soundflower ile oluşturulmuş aggregate device) isminde
+    /// "loopback" veya "aggregate" geçen bir input cihazı olarak görünür.
+    ///
+    /// İkisi de bulunamazsa: cihazı kuramadığımızı belirten açıklayıcı hata.
+    fn find_loopback_device(host: &cpal::Host) -> Result<cpal::Device, String> {
+        let needle: &[&str] = if cfg!(target_os = "macos") {
+            &["loopback", "aggregate"]
+        } else {
+            &[".monitor", "monitor of"]
+        };
+
+        let devices = host
+            .devices()
+            .map_err(|e| format!("Failed to enumerate audio devices: {}", e))?;
+
+        for device in devices {
+            let Ok(name) = device.name() else { continue };
+            let lower = name.to_lowercase();
+            if needle.iter().any(|n| lower.contains(n)) {
+                return Ok(device);
+            }
+        }
+
+        Err(
+            "No loopback/monitor input device found. On Linux, enable a PulseAudio/PipeWire \
+             monitor source; on macOS, create an aggregate/loopback device (e.g. BlackHole) \
+             and select it as an input."
+                .into(),
+        )
+    }
+
+    /// Ana capture döngüsü — background thread'de çalışır.
+    ///
+    /// cpal'in callback-driven modeli WASAPI'nin event-driven modeliyle aynı
+    /// fikri paylaşır: cihaz yeni veri verdikçe bir callback tetiklenir. Biz
+    /// callback'te gelen f32 sample'ları paylaşılan bir `VecDeque`'ye yazıp,
+    /// bu thread'de `config.chunk_ms`'lik chunk'lara bölüp IPC ile yayınlıyoruz.
+    ///
+    /// `paused` true iken stream canlı kalır (callback veri biriktirmeye
+    /// devam eder) ama emit atlanır — WASAPI tarafıyla aynı pause semantiği.
+    ///
+    /// `config.sample_rate`/`config.channels` cihazın desteklediği aralığa
+    /// uyuyorsa aynen kullanılır; uymuyorsa cihazın ilk f32 config'i, onun
+    /// en yüksek sample rate'i ve kanal sayısıyla kullanılır (cpal,
+    /// WASAPI'nin `autoconvert`'i gibi bir köprü sunmaz; istenen formata en
+    /// yakın config aranmaz). Bu
+    /// fallback sessiz değildir: gerçekte negotiate edilen `sample_rate`/
+    /// `channels`, her `PcmChunk`'ın kendi alanlarında frontend'e bildirilir
+    /// — frontend her zaman `config.sample_rate` yerine chunk'ın kendi
+    /// alanlarını decode için kullanmalı, aksi halde yanlış hızda/kanal
+    /// sayısıyla bozuk ses üretilir.
+    /// `config.chunk_ms` her durumda korunur; chunk boyutu gerçekte seçilen
+    /// sample rate/kanal sayısına göre hesaplanır.
+    pub fn capture_loop(
+        app: AppHandle,
+        running: Arc<AtomicBool>,
+        paused: Arc<AtomicBool>,
+        id: CaptureId,
+        requested: CaptureConfig,
+    ) -> Result<(), String> {
+        let host = cpal::default_host();
+        let device = find_loopback_device(&host)?;
+        let stream_config = select_input_config(&device, &requested)?;
+
+        let channels = stream_config.channels() as usize;
+        let sample_rate = stream_config.sample_rate().0 as usize;
+        let config: cpal::StreamConfig = stream_config.into();
+
+        // Chunk boyutu negotiate edilen (gerçek) sample rate'e göre
+        // hesaplanır — `requested.sample_rate`'e göre değil, çünkü
+        // `select_input_config` tam eşleşme bulamazsa farklı bir rate'e
+        // düşebilir. `CaptureConfig::chunk_frames()` burada da kullanılır
+        // (WASAPI backend'iyle aynı formül, sabit kodlanmış aritmetik yok).
+        let negotiated = CaptureConfig {
+            sample_rate: sample_rate as u32,
+            channels: channels as u16,
+            chunk_ms: requested.chunk_ms,
+        };
+        let chunk_frames = negotiated.chunk_frames();
+        let chunk_samples = chunk_frames * channels;
+
+        let sample_queue: Arc<Mutex<VecDeque<f32>>> = Arc::new(Mutex::new(VecDeque::new()));
+        let queue_for_callback = sample_queue.clone();
+
+        let err_running = running.clone();
+        let stream = device
+            .build_input_stream(
+                &config,
+                move |data: &[f32], _| {
+                    if let Ok(mut queue) = queue_for_callback.lock() {
+                        queue.extend(data.iter().copied());
+                    }
+                },
+                move |e| {
+                    eprintln!("[AudioCapture] cpal stream error: {}", e);
+                    err_running.store(false, Ordering::SeqCst);
+                },
+                None,
+            )
+            .map_err(|e| format!("Failed to build input stream: {}", e))?;
+
+        stream
+            .play()
+            .map_err(|e| format!("Failed to start input stream: {}", e))?;
+
+        // ─── Capture Loop ───
+        // cpal callback arka planda kendi thread'inde çalışır; burada sadece
+        // birikmiş sample'ları periyodik olarak chunk'layıp IPC ile yayınlıyoruz.
+        //
+        // `sleep` her zaman `chunk_ms`'i bir miktar aşar (scheduler gecikmesi),
+        // callback ise tam `chunk_ms`'te bir chunk'lık veri ekliyor — bu yüzden
+        // uyanışta TEK chunk çekmek üretici/tüketici oranını bozar ve
+        // `sample_queue` sınırsız büyür (ses giderek gerçek zamandan geride
+        // kalır). WASAPI backend'indeki gibi (bkz. yukarıdaki `while
+        // sample_queue.len() >= chunk_bytes`) uyanışta biriken TÜM tam
+        // chunk'ları drene ediyoruz.
+        'outer: while running.load(Ordering::SeqCst) {
+            std::thread::sleep(std::time::Duration::from_millis(requested.chunk_ms as u64));
+
+            loop {
+                let Ok(mut queue) = sample_queue.lock() else {
+                    break;
+                };
+                if queue.len() < chunk_samples {
+                    break;
+                }
+                let chunk: Vec<f32> = queue.drain(..chunk_samples).collect();
+                drop(queue);
+
+                if paused.load(Ordering::SeqCst) {
+                    continue;
+                }
+
+                let i16_samples = f32_samples_to_i16(&chunk);
+                if app
+                    .emit(
+                        "audio-pcm",
+                        PcmChunk {
+                            id,
+                            sample_rate: sample_rate as u32,
+                            channels: channels as u16,
+                            samples: i16_samples,
+                        },
+                    )
+                    .is_err()
+                {
+                    running.store(false, Ordering::SeqCst);
+                    break 'outer;
+                }
+            }
+        }
+
+        drop(stream);
+        Ok(())
+    }
+
+    /// `requested` ile eşleşen loopback input config'ini, yoksa ilk f32
+    /// config'i seçer.
+    ///
+    /// `build_input_stream` bu dosyada f32 callback ile çağrılır, bu yüzden
+    /// burada SADECE `SampleFormat::F32` config'ler arasından seçim yapılır —
+    /// cihazın native formatı I16/U16 ise o config'i hiç adaylığa almayız,
+    /// aksi halde `build_input_stream` sessizce format uyuşmazlığıyla
+    /// başarısız olur ve capture hiç veri üretmez.
+    ///
+    /// Önce `requested.sample_rate`/`requested.channels`'ı destekleyen bir
+    /// f32 config aranır. Bulunamazsa f32 config'ler arasından ilkinin en
+    /// yüksek sample rate'i kullanılır — cpal shared-mode autoconvert
+    /// sunmadığı için tam eşleşme garanti edilemez.
+    fn select_input_config(
+        device: &cpal::Device,
+        requested: &CaptureConfig,
+    ) -> Result<cpal::SupportedStreamConfig, String> {
+        let configs: Vec<_> = device
+            .supported_input_configs()
+            .map_err(|e| format!("Failed to query supported input configs: {}", e))?
+            .filter(|c| c.sample_format() == cpal::SampleFormat::F32)
+            .collect();
+
+        let exact = configs.iter().find(|c| {
+            c.channels() == requested.channels
+                && c.min_sample_rate().0 <= requested.sample_rate
+                && requested.sample_rate <= c.max_sample_rate().0
+        });
+        if let Some(c) = exact {
+            return Ok(c.clone().with_sample_rate(cpal::SampleRate(requested.sample_rate)));
+        }
+
+        configs
+            .into_iter()
+            .next()
+            .map(|c| c.with_max_sample_rate())
+            .ok_or_else(|| {
+                "Loopback device has no supported f32 input configs".to_string()
+            })
+    }
+
+    /// Loopback cihazının desteklediği (sample_rate, channels) kombinasyonlarını
+    /// enumerate eder — her config aralığının min ve max sample rate'i ayrı
+    /// birer giriş olarak döner.
+    ///
+    /// `select_input_config`'in yapabildiği gibi yalnızca `SampleFormat::F32`
+    /// config'ler raporlanır — frontend'e I16/U16-only bir format önerip de
+    /// `capture_loop`'un onu hiç açamaması istenmez.
+    pub fn query_supported_formats() -> Result<Vec<super::SupportedFormat>, String> {
+        let host = cpal::default_host();
+        let device = find_loopback_device(&host)?;
+
+        let mut formats = Vec::new();
+        for config in device
+            .supported_input_configs()
+            .map_err(|e| format!("Failed to query supported input configs: {}", e))?
+            .filter(|c| c.sample_format() == cpal::SampleFormat::F32)
+        {
+            let channels = config.channels();
+            for rate in [config.min_sample_rate().0, config.max_sample_rate().0] {
+                formats.push(super::SupportedFormat {
+                    sample_rate: rate,
+                    channels,
+                });
+            }
+        }
+        formats.dedup_by(|a, b| a.sample_rate == b.sample_rate && a.channels == b.channels);
+
+        Ok(formats)
+    }
 }
@@ -0,0 +1,307 @@
+/// Low-latency WASAPI playback module for incoming remote PCM.
+///
+/// `audio_capture` bu sürece gelen sesi yakalar; bu modül ise tersini yapar —
+/// remote peer'lardan gelen PCM'i oynatır. WASAPI shared-mode event-driven
+/// render kullanılır (Chromium'un `audio_low_latency_output_win.cc`
+/// kaynağındaki düşük gecikmeli output deseniyle aynı fikir): `AudioClient`
+/// `Direction::Render` ile açılır, her event sinyalinde endpoint'in
+/// bildirdiği kadar frame `IAudioRenderClient`'a yazılır.
+///
+/// Veri akışı:
+/// ```text
+/// Remote peer (LiveKit) → Frontend → Tauri IPC (invoke push_audio_pcm)
+///   → i16→f32 dönüşüm (Rust)
+///   → bounded ring buffer
+///   → WASAPI render event → IAudioRenderClient::write_to_device
+///   → varsayılan çalma cihazı
+/// ```
+///
+/// Neden ring buffer + event-driven render?
+/// Frontend'in PCM gönderme hızı (network jitter'a bağlı) ile WASAPI'nin
+/// render event hızı (cihaz saatine bağlı) birbirine tam senkron değildir.
+/// Bounded ring buffer bu ikisini ayırır: `push_audio_pcm` arkaya yazar,
+/// render thread öne ihtiyaç duyduğu kadar okur. Buffer boşsa sessizlik
+/// (0 değerli frame) yazılır — alttan beslenmeyi (underrun/click) önler.
+///
+/// Cihaz değişimi (AUDCLNT_E_DEVICE_INVALIDATED):
+/// Kullanıcı varsayılan çalma cihazını değiştirirse (ör. kulaklık takma)
+/// WASAPI bu hatayı döner. Render loop bunu yakalar, client'ı yıkıp
+/// varsayılan cihazla sessizce yeniden kurar — playback kesilmez.
+///
+/// Platform desteği: Windows-only (WASAPI render). macOS/Linux için
+/// `start()` açıklayıcı bir hata döner; `audio_capture`'daki cpal backend'in
+/// aksine burada henüz bir cpal output karşılığı eklenmedi.
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Ring buffer'da tutulabilecek azami sample sayısı.
+///
+/// 48kHz stereo f32: 1 saniyelik ses = 48000 × 2 = 96000 sample.
+/// Bunu aşan push'lar en eski sample'ları düşürür — network'ten gelen
+/// veri render hızından kalıcı olarak hızlıysa buffer sonsuza kadar büyümez,
+/// bunun yerine en yeni sesi tutmayı tercih ederiz (gecikme birikmesin diye).
+const MAX_BUFFERED_SAMPLES: usize = 96_000;
+
+/// Remote PCM playback controller.
+///
+/// `start()` bir background render thread başlatır, `stop()` ile durdurulur.
+/// `push()` frontend'den gelen i16 sample'ları f32'ye çevirip ring buffer'a yazar.
+pub struct AudioPlayback {
+    /// Background render thread'in çalışma durumu.
+    running: Arc<AtomicBool>,
+    /// Render thread'in okuduğu, `push()`'un yazdığı paylaşılan ring buffer.
+    /// Interleaved f32 — WASAPI render format'ıyla aynı.
+    ring: Arc<Mutex<VecDeque<f32>>>,
+}
+
+impl AudioPlayback {
+    pub fn new() -> Self {
+        Self {
+            running: Arc::new(AtomicBool::new(false)),
+            ring: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    /// Playback'in şu an çalışıp çalışmadığını kontrol eder.
+    #[allow(dead_code)]
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Frontend'den gelen interleaved i16 PCM'i ring buffer'a ekler.
+    ///
+    /// i16 → f32 dönüşümü burada yapılır (WASAPI render format'ı f32).
+    /// Buffer `MAX_BUFFERED_SAMPLES`'i aşarsa en eski sample'lar düşürülür —
+    /// gecikme birikmesin diye her zaman en yeni sesi tutarız.
+    pub fn push(&self, samples: Vec<i16>) -> Result<(), String> {
+        let mut ring = self
+            .ring
+            .lock()
+            .map_err(|e| format!("Ring buffer lock failed: {}", e))?;
+
+        ring.extend(samples.iter().map(|&s| s as f32 / 32767.0));
+
+        let overflow = ring.len().saturating_sub(MAX_BUFFERED_SAMPLES);
+        if overflow > 0 {
+            ring.drain(..overflow);
+        }
+
+        Ok(())
+    }
+
+    /// WASAPI render başlatır.
+    ///
+    /// Hata durumları:
+    /// - Zaten çalışıyorsa: "Audio playback already running"
+    /// - WASAPI hatası (cihaz yok vb.): thread log'layıp kapanır
+    #[cfg(windows)]
+    pub fn start(&self) -> Result<(), String> {
+        if self.running.load(Ordering::SeqCst) {
+            return Err("Audio playback already running".into());
+        }
+
+        self.running.store(true, Ordering::SeqCst);
+        let running = self.running.clone();
+        let ring = self.ring.clone();
+
+        // Background thread: WASAPI render loop.
+        // IAudioRenderClient !Send ve !Sync — tüm WASAPI işlemleri bu
+        // thread'de kalmalı. Ring buffer Mutex ile korunduğu için güvenle
+        // paylaşılır.
+        std::thread::spawn(move || {
+            if let Err(e) = wasapi_render::render_loop(running.clone(), ring) {
+                eprintln!("[AudioPlayback] Render loop error: {}", e);
+            }
+            running.store(false, Ordering::SeqCst);
+        });
+
+        Ok(())
+    }
+
+    /// macOS/Linux: WASAPI olmadığı için playback henüz desteklenmiyor.
+    #[cfg(not(windows))]
+    pub fn start(&self) -> Result<(), String> {
+        Err("Audio playback is only supported on Windows".into())
+    }
+
+    /// Background thread'deki render loop'u durdurur.
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// Windows-only: WASAPI render loop implementation
+// ═══════════════════════════════════════════════════════════════════════
+
+#[cfg(windows)]
+mod wasapi_render {
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+    use wasapi::*;
+
+    /// Ana render döngüsü — background thread'de çalışır.
+    ///
+    /// 1. COM init (MTA)
+    /// 2. Varsayılan render cihazını aç, f32 stereo 48kHz ile initialize et
+    /// 3. Bir buffer period'luk sessizlik prefill et (ilk event'ten önce
+    ///    buffer boş kalmasın diye)
+    /// 4. Event bekle → endpoint'in bildirdiği kadar frame'i ring buffer'dan
+    ///    çek (yetersizse sessizlikle doldur) → cihaza yaz
+    /// 5. AUDCLNT_E_DEVICE_INVALIDATED görülürse client'ı yıkıp varsayılan
+    ///    cihazla sessizce yeniden kur (playback kesilmeden devam eder)
+    /// 6. running flag false olana kadar tekrarla
+    pub fn render_loop(
+        running: Arc<AtomicBool>,
+        ring: Arc<Mutex<VecDeque<f32>>>,
+    ) -> Result<(), String> {
+        initialize_mta()
+            .ok()
+            .map_err(|e| format!("COM initialization failed: {}", e))?;
+
+        let desired_format = WaveFormat::new(32, 32, &SampleType::Float, 48000, 2, None);
+        let blockalign = desired_format.get_blockalign() as usize;
+        let channels = 2usize;
+
+        let mut session = open_render_session(&desired_format)?;
+
+        while running.load(Ordering::SeqCst) {
+            if session.h_event.wait_for_event(100).is_err() {
+                continue;
+            }
+
+            let frames_available = match session.audio_client.get_available_space_in_frames() {
+                Ok(frames) => frames as usize,
+                Err(e) => {
+                    if is_device_invalidated(e.as_ref()) {
+                        match open_render_session(&desired_format) {
+                            Ok(new_session) => {
+                                session = new_session;
+                                continue;
+                            }
+                            Err(e) => {
+                                eprintln!("[AudioPlayback] Failed to reinit after device change: {}", e);
+                                break;
+                            }
+                        }
+                    }
+                    eprintln!("[AudioPlayback] Failed to query available frames: {}", e);
+                    break;
+                }
+            };
+
+            if frames_available == 0 {
+                continue;
+            }
+
+            // Ring buffer'dan frames_available kadar frame çek; yetersizse
+            // kalanı sessizlikle (0.0) doldur — alttan besleme olmasın.
+            let needed_samples = frames_available * channels;
+            let mut data = Vec::with_capacity(needed_samples);
+            if let Ok(mut ring) = ring.lock() {
+                let take = needed_samples.min(ring.len());
+                data.extend(ring.drain(..take));
+            }
+            data.resize(needed_samples, 0.0);
+
+            let bytes: Vec<u8> = data.iter().flat_map(|s| s.to_le_bytes()).collect();
+
+            if let Err(e) =
+                session
+                    .render_client
+                    .write_to_device(frames_available, blockalign, &bytes, None)
+            {
+                if is_device_invalidated(e.as_ref()) {
+                    match open_render_session(&desired_format) {
+                        Ok(new_session) => {
+                            session = new_session;
+                            continue;
+                        }
+                        Err(e) => {
+                            eprintln!("[AudioPlayback] Failed to reinit after device change: {}", e);
+                            break;
+                        }
+                    }
+                }
+                eprintln!("[AudioPlayback] Write to device failed: {}", e);
+                break;
+            }
+        }
+
+        let _ = session.audio_client.stop_stream();
+        Ok(())
+    }
+
+    /// Tek bir render oturumunun açık tutulması gereken WASAPI handle'ları.
+    struct RenderSession {
+        audio_client: AudioClient,
+        render_client: AudioRenderClient,
+        h_event: Handle,
+    }
+
+    /// Varsayılan render cihazını açar, initialize eder, bir buffer period'luk
+    /// sessizlik prefill eder ve stream'i başlatır.
+    ///
+    /// Hem ilk kuruluşta hem de `AUDCLNT_E_DEVICE_INVALIDATED` sonrası
+    /// yeniden kuruluşta kullanılır.
+    fn open_render_session(desired_format: &WaveFormat) -> Result<RenderSession, String> {
+        let device = get_default_device(&Direction::Render)
+            .map_err(|e| format!("Failed to get default render device: {}", e))?;
+        let mut audio_client = device
+            .get_iaudioclient()
+            .map_err(|e| format!("Failed to get audio client: {}", e))?;
+
+        let mode = StreamMode::EventsShared {
+            autoconvert: true,
+            buffer_duration_hns: 0,
+        };
+        audio_client
+            .initialize_client(desired_format, &Direction::Render, &mode)
+            .map_err(|e| format!("Client initialization failed: {}", e))?;
+
+        let h_event = audio_client
+            .set_get_eventhandle()
+            .map_err(|e| format!("Failed to get event handle: {}", e))?;
+        let render_client = audio_client
+            .get_audiorenderclient()
+            .map_err(|e| format!("Failed to get render client: {}", e))?;
+
+        // Bir buffer period'luk sessizlik prefill et — ilk render event'i
+        // gelmeden önce buffer'ın boş kalmasını (ve anlık underrun click'ini)
+        // önler.
+        let blockalign = desired_format.get_blockalign() as usize;
+        let buffer_frame_count = audio_client
+            .get_bufferframecount()
+            .map_err(|e| format!("Failed to get buffer frame count: {}", e))? as usize;
+        let silence = vec![0u8; buffer_frame_count * blockalign];
+        render_client
+            .write_to_device(buffer_frame_count, blockalign, &silence, None)
+            .map_err(|e| format!("Failed to prefill silence: {}", e))?;
+
+        audio_client
+            .start_stream()
+            .map_err(|e| format!("Failed to start render stream: {}", e))?;
+
+        Ok(RenderSession {
+            audio_client,
+            render_client,
+            h_event,
+        })
+    }
+
+    /// Hatanın `AUDCLNT_E_DEVICE_INVALIDATED` HRESULT'una karşılık gelip
+    /// gelmediğini kontrol eder — varsayılan cihaz değiştiğinde (ör.
+    /// kulaklık takılması/çıkarılması) WASAPI bu hatayı döner.
+    ///
+    /// `Display` metnine (yerelleştirilmiş sistem mesajı / sayısal HRESULT)
+    /// değil, doğrudan HRESULT koduna bakıyoruz — Display string'i neredeyse
+    /// hiçbir zaman sembolik sabit adını içermez, bu yüzden string eşleşmesi
+    /// pratikte asla tutmaz ve reconnect mantığı hiç tetiklenmezdi.
+    fn is_device_invalidated(err: &(dyn std::error::Error + 'static)) -> bool {
+        err.downcast_ref::<windows::core::Error>()
+            .map(|e| e.code() == windows::Win32::Media::Audio::AUDCLNT_E_DEVICE_INVALIDATED)
+            .unwrap_or(false)
+    }
+}
@@ -15,77 +15,245 @@
 /// Discord benzeri davranış: arka planda çalışmaya devam eder (WS, voice vb.)
 ///
 /// Audio Capture:
-/// WASAPI per-process audio capture (Windows-only).
-/// Screen share sırasında kendi uygulamamızın sesini hariç tutarak
-/// sistem sesini yakalar → voice chat echo olmaz.
-/// Frontend'den `invoke("start_audio_capture")` / `invoke("stop_audio_capture")`
-/// ile kontrol edilir.
+/// Windows'ta WASAPI per-process loopback, macOS/Linux'ta cpal üzerinden
+/// loopback/monitor device capture. Screen share sırasında sistem sesini
+/// yakalar; Windows'ta kendi uygulamamızın sesi otomatik hariç tutulur
+/// (echo olmaz), diğer platformlarda bkz. `audio_capture` modül dokümanı.
+/// Birden fazla eşzamanlı oturum desteklenir — her `start_audio_capture*`
+/// çağrısı kendi `CaptureId`'sini döner, `stop`/`pause`/`resume_audio_capture`
+/// bu id'yi alır. Frontend'den `invoke("start_audio_capture")` vb. ile
+/// kontrol edilir. Sample rate/kanal sayısı/chunk süresi bir `CaptureConfig`
+/// ile negotiate edilebilir (`query_supported_formats` ile önce cihazın
+/// destekleri sorgulanabilir); verilmezse önceki sabit kodlanmış varsayılanlar
+/// (48kHz stereo, 20ms) kullanılır.
+///
+/// Audio Playback:
+/// Remote peer'lardan gelen PCM'i WASAPI low-latency render ile çalar
+/// (Windows-only). Frontend `invoke("start_audio_playback")` ile başlatır,
+/// `invoke("push_audio_pcm", { samples })` ile sürekli PCM besler,
+/// `invoke("stop_audio_playback")` ile durdurur.
 
 mod audio_capture;
+mod audio_playback;
 
-use std::sync::Mutex;
 use tauri::{
     Manager, WindowEvent,
     menu::{Menu, MenuItem},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
 };
 
-/// Tauri managed state: WASAPI audio capture controller.
+/// Tauri managed state: çoklu-oturum audio capture yöneticisi.
+///
+/// `CaptureManager` kendi içinde `Mutex`/`AtomicU64` kullanır, bu yüzden
+/// dışarıdan ayrıca bir `Mutex` sarmaya gerek yok — doğrudan `Sync`'tir.
+struct AudioCaptureState(audio_capture::CaptureManager);
+
+/// Tauri managed state: WASAPI audio playback controller.
 ///
-/// Mutex ile sarılır çünkü Tauri command'ları farklı thread'lerden çağrılabilir.
-/// AudioCapture içindeki AtomicBool thread-safe olsa da, Tauri State<T> için
-/// Sync trait gerekir — Mutex bunu sağlar.
-struct AudioCaptureState(Mutex<audio_capture::AudioCapture>);
+/// `AudioPlayback` içindeki `Arc<AtomicBool>`/`Arc<Mutex<_>>` zaten `Sync`'tir;
+/// dıştaki `Mutex`, farklı thread'lerden gelen Tauri command'larının
+/// `start`/`stop`/`push` çağrılarını sıraya sokar (ör. iki eşzamanlı
+/// `start`'ın ikisinin de "çalışmıyor" görüp thread başlatmasını önler).
+struct AudioPlaybackState(std::sync::Mutex<audio_playback::AudioPlayback>);
 
 /// Tauri command: WASAPI per-process audio capture başlat.
 ///
 /// Frontend'den çağrılır:
 /// ```typescript
-/// await invoke("start_audio_capture");
+/// const id = await invoke("start_audio_capture");
 /// ```
 ///
-/// Background thread'de WASAPI capture loop başlatır.
-/// 48kHz stereo i16 PCM chunk'ları "audio-pcm" event'i ile frontend'e gönderilir.
+/// Background thread'de capture loop başlatır (Windows'ta WASAPI, kendi
+/// PID'imizi EXCLUDE modda kullanır — echo önleme; macOS/Linux'ta cpal
+/// loopback/monitor, PID yok sayılır). Dönen `CaptureId`, bu oturumu
+/// `stop_audio_capture`/`pause_audio_capture`/`resume_audio_capture`'a
+/// geçirmek için saklanmalı.
+/// `config` verilmezse varsayılan (48kHz stereo, 20ms chunk) kullanılır —
+/// mevcut frontend çağrıları (`invoke("start_audio_capture")`) değişmeden çalışır.
+/// PCM chunk'ları "audio-pcm" event'i ile frontend'e gönderilir.
 /// Hata durumunda string mesaj döner (frontend toast gösterebilir).
 #[tauri::command]
 fn start_audio_capture(
     app: tauri::AppHandle,
     state: tauri::State<AudioCaptureState>,
+    config: Option<audio_capture::CaptureConfig>,
+) -> Result<audio_capture::CaptureId, String> {
+    state
+        .0
+        .start(app, std::process::id(), false, config.unwrap_or_default())
+}
+
+/// Tauri command: tek bir uygulamanın (process tree'sinin) sesini capture et.
+///
+/// Frontend'den çağrılır:
+/// ```typescript
+/// const id = await invoke("start_audio_capture_for_pid", { pid: 1234, include: true });
+/// ```
+///
+/// `include = true`: sadece `pid` yakalanır (INCLUDE mode) — örn. sadece bir
+/// oyunun veya tarayıcı sekmesinin sesini paylaşmak için.
+/// `include = false`: `pid` HARİÇ her şey yakalanır (EXCLUDE mode) — bu mod
+/// normalde `start_audio_capture` üzerinden kendi PID'imizle kullanılır, ama
+/// burada da istenirse başka bir PID ile çağrılabilir.
+/// `start_audio_capture` zaten çalışıyorken de çağrılabilir — her oturum
+/// kendi bağımsız `CaptureId`'sini alır (ör. bir oyun akışı + sistem akışı).
+/// `config` verilmezse varsayılan (48kHz stereo, 20ms chunk) kullanılır.
+#[tauri::command]
+fn start_audio_capture_for_pid(
+    app: tauri::AppHandle,
+    state: tauri::State<AudioCaptureState>,
+    pid: u32,
+    include: bool,
+    config: Option<audio_capture::CaptureConfig>,
+) -> Result<audio_capture::CaptureId, String> {
+    state.0.start(app, pid, include, config.unwrap_or_default())
+}
+
+/// Tauri command: capture'ın açacağı loopback cihazının desteklediği
+/// formatları listele.
+///
+/// Frontend'den çağrılır:
+/// ```typescript
+/// const formats = await invoke("query_supported_formats");
+/// ```
+///
+/// Dönen liste, kullanıcının `start_audio_capture`/`start_audio_capture_for_pid`'e
+/// geçireceği bir `CaptureConfig` oluştururken cihazın gerçekten destekleyeceği
+/// bir (sample_rate, channels) kombinasyonu seçebilmesini sağlar.
+#[tauri::command]
+fn query_supported_formats() -> Result<Vec<audio_capture::SupportedFormat>, String> {
+    audio_capture::query_supported_formats()
+}
+
+/// Tauri command: aktif audio session'larını listele.
+///
+/// Frontend'den çağrılır:
+/// ```typescript
+/// const sessions = await invoke("list_audio_sessions");
+/// ```
+///
+/// Dönen liste, kullanıcının `start_audio_capture_for_pid` için raw PID
+/// bilmesine gerek kalmadan bir picker'da uygulama seçebilmesini sağlar.
+#[tauri::command]
+fn list_audio_sessions() -> Result<Vec<audio_capture::AudioSessionInfo>, String> {
+    audio_capture::list_audio_sessions()
+}
+
+/// Tauri command: bir capture oturumunu durdur.
+///
+/// Frontend'den çağrılır:
+/// ```typescript
+/// await invoke("stop_audio_capture", { id });
+/// ```
+///
+/// İlgili background thread'e dur sinyali verir, oturumu manager'dan çıkarır.
+/// `id` zaten durmuşsa/hiç var olmamışsa sessizce başarılı döner.
+#[tauri::command]
+fn stop_audio_capture(
+    state: tauri::State<AudioCaptureState>,
+    id: audio_capture::CaptureId,
 ) -> Result<(), String> {
-    let capture = state
+    state.0.stop(id)
+}
+
+/// Tauri command: bir capture oturumunu duraklat.
+///
+/// Frontend'den çağrılır:
+/// ```typescript
+/// await invoke("pause_audio_capture", { id });
+/// ```
+///
+/// WASAPI/cpal stream'i canlı kalır, sadece "audio-pcm" emit'i durur —
+/// bu yüzden `resume_audio_capture` anında devam edebilir.
+#[tauri::command]
+fn pause_audio_capture(
+    state: tauri::State<AudioCaptureState>,
+    id: audio_capture::CaptureId,
+) -> Result<(), String> {
+    state.0.pause(id)
+}
+
+/// Tauri command: duraklatılmış bir capture oturumunu devam ettir.
+///
+/// Frontend'den çağrılır:
+/// ```typescript
+/// await invoke("resume_audio_capture", { id });
+/// ```
+#[tauri::command]
+fn resume_audio_capture(
+    state: tauri::State<AudioCaptureState>,
+    id: audio_capture::CaptureId,
+) -> Result<(), String> {
+    state.0.resume(id)
+}
+
+/// Tauri command: remote PCM playback başlat.
+///
+/// Frontend'den çağrılır:
+/// ```typescript
+/// await invoke("start_audio_playback");
+/// ```
+///
+/// Background thread'de WASAPI render loop başlatır. `push_audio_pcm` ile
+/// beslenen sample'lar bu thread tarafından varsayılan çalma cihazına yazılır.
+#[tauri::command]
+fn start_audio_playback(state: tauri::State<AudioPlaybackState>) -> Result<(), String> {
+    let playback = state
         .0
         .lock()
         .map_err(|e| format!("State lock failed: {}", e))?;
-    capture.start(app)
+    playback.start()
 }
 
-/// Tauri command: WASAPI audio capture durdur.
+/// Tauri command: remote PCM playback durdur.
 ///
 /// Frontend'den çağrılır:
 /// ```typescript
-/// await invoke("stop_audio_capture");
+/// await invoke("stop_audio_playback");
 /// ```
-///
-/// AtomicBool flag'i false yapar → background thread temiz kapanır.
-/// Capture zaten çalışmıyorsa sessizce başarılı döner.
 #[tauri::command]
-fn stop_audio_capture(state: tauri::State<AudioCaptureState>) -> Result<(), String> {
-    let capture = state
+fn stop_audio_playback(state: tauri::State<AudioPlaybackState>) -> Result<(), String> {
+    let playback = state
         .0
         .lock()
         .map_err(|e| format!("State lock failed: {}", e))?;
-    capture.stop();
+    playback.stop();
     Ok(())
 }
 
+/// Tauri command: çalınacak PCM sample'larını besle.
+///
+/// Frontend'den çağrılır:
+/// ```typescript
+/// await invoke("push_audio_pcm", { samples });
+/// ```
+///
+/// `samples`: interleaved stereo i16 — remote peer'dan gelen ses. Ring
+/// buffer'a eklenmeden önce f32'ye çevrilir. `start_audio_playback`
+/// çağrılmamışken de push edilebilir — sample'lar playback başlayana kadar
+/// buffer'da birikir (üst sınırı aşarsa en eskiler düşer).
+#[tauri::command]
+fn push_audio_pcm(
+    state: tauri::State<AudioPlaybackState>,
+    samples: Vec<i16>,
+) -> Result<(), String> {
+    let playback = state
+        .0
+        .lock()
+        .map_err(|e| format!("State lock failed: {}", e))?;
+    playback.push(samples)
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         // ─── Managed State ───
-        // AudioCaptureState: WASAPI capture controller.
+        // AudioCaptureState: çoklu-oturum capture manager.
         // Tauri command'ları State<AudioCaptureState> parametresi ile erişir.
-        .manage(AudioCaptureState(Mutex::new(
-            audio_capture::AudioCapture::new(),
+        .manage(AudioCaptureState(audio_capture::CaptureManager::new()))
+        // AudioPlaybackState: WASAPI render controller.
+        .manage(AudioPlaybackState(std::sync::Mutex::new(
+            audio_playback::AudioPlayback::new(),
         )))
         // ─── Tauri Commands ───
         // Frontend'den invoke() ile çağrılabilecek Rust fonksiyonları.
@@ -93,7 +261,15 @@ pub fn run() {
         // kaydeder, type-safe deserialization sağlar.
         .invoke_handler(tauri::generate_handler![
             start_audio_capture,
-            stop_audio_capture
+            start_audio_capture_for_pid,
+            query_supported_formats,
+            list_audio_sessions,
+            stop_audio_capture,
+            pause_audio_capture,
+            resume_audio_capture,
+            start_audio_playback,
+            stop_audio_playback,
+            push_audio_pcm
         ])
         .setup(|app| {
             #[cfg(desktop)]